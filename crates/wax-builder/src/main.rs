@@ -1,13 +1,18 @@
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use wax_core::{reader::WaxReader, WaxHeader, WAX_MAGIC};
+use wax_core::{codec::Codec, reader::WaxReader, seekable, seekable::FrameEntry, WaxHeader, WAX_MAGIC};
 use rusqlite::Connection;
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{Seek, SeekFrom, Write};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use walkdir::WalkDir;
-use zerocopy::AsBytes;
+use zerocopy::{AsBytes, FromBytes};
 use indicatif::{ProgressBar, ProgressStyle};
+use tempfile::NamedTempFile;
+
+#[cfg(feature = "fuse")]
+mod mount;
 
 #[derive(Parser, Debug)]
 #[command(author = "Neon Digital Systems", version = "1.1.0", about = "High-performance WAX Archive Builder")]
@@ -16,6 +21,29 @@ struct Args {
     cmd: Commands,
 }
 
+/// The codec choices exposed on the CLI; `Auto` defers to `build_archive`'s
+/// per-file heuristic instead of forcing one codec on everything.
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+enum CodecArg {
+    Auto,
+    Store,
+    Zstd,
+    Xz,
+    Bzip2,
+}
+
+impl CodecArg {
+    fn to_codec(self) -> Option<Codec> {
+        match self {
+            CodecArg::Auto => None,
+            CodecArg::Store => Some(Codec::Store),
+            CodecArg::Zstd => Some(Codec::Zstd),
+            CodecArg::Xz => Some(Codec::Xz),
+            CodecArg::Bzip2 => Some(Codec::Bzip2),
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 enum Commands {
     /// Compress a directory into a .wax archive
@@ -24,6 +52,18 @@ enum Commands {
         input: PathBuf,
         #[arg(short, long)]
         output: PathBuf,
+        /// Force a single codec for every file instead of the automatic heuristic
+        #[arg(long, value_enum, default_value_t = CodecArg::Auto)]
+        codec: CodecArg,
+        /// Compression level passed to the chosen codec
+        #[arg(long, default_value_t = 3)]
+        level: i32,
+        /// Encrypt blobs and the index with a password-derived key
+        #[arg(long)]
+        password: Option<String>,
+        /// Read the password from a file instead of passing it on the command line
+        #[arg(long, conflicts_with = "password")]
+        key_file: Option<PathBuf>,
     },
     /// Read a specific file out of a .wax archive
     Read {
@@ -31,16 +71,82 @@ enum Commands {
         archive: PathBuf,
         #[arg(short, long)]
         file: String,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
     },
     /// List all files inside an archive
     Ls {
         #[arg(short, long)]
         archive: PathBuf,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
     },
     /// Inspect archive metadata
     Inspect {
         #[arg(short, long)]
         archive: PathBuf,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Check every blob's checksum and confirm the index is intact
+    Verify {
+        #[arg(short, long)]
+        archive: PathBuf,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Mount an archive read-only as a FUSE filesystem
+    #[cfg(feature = "fuse")]
+    Mount {
+        #[arg(short, long)]
+        archive: PathBuf,
+        #[arg(short, long)]
+        mountpoint: PathBuf,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Add or update files in an existing .wax archive without a full rebuild
+    Append {
+        #[arg(short, long)]
+        archive: PathBuf,
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Force a single codec for every new file instead of the automatic heuristic
+        #[arg(long, value_enum, default_value_t = CodecArg::Auto)]
+        codec: CodecArg,
+        /// Compression level passed to the chosen codec
+        #[arg(long, default_value_t = 3)]
+        level: i32,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
+        /// Read the password from a file instead of passing it on the command line
+        #[arg(long, conflicts_with = "password")]
+        key_file: Option<PathBuf>,
+    },
+    /// Remove a single file's entry from an existing .wax archive's index
+    Remove {
+        #[arg(short, long)]
+        archive: PathBuf,
+        #[arg(short, long)]
+        file: String,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
+    },
+    /// Full-text search over extracted file contents and metadata
+    Search {
+        #[arg(short, long)]
+        archive: PathBuf,
+        query: String,
+        /// Password for an encrypted archive
+        #[arg(long)]
+        password: Option<String>,
     }
 }
 
@@ -48,23 +154,154 @@ fn main() -> Result<()> {
     let args = Args::parse();
 
     match args.cmd {
-        Commands::Build { input, output } => build_archive(input, output),
-        Commands::Read { archive, file } => read_file(archive, file),
-        Commands::Ls { archive } => list_archive(archive),
-        Commands::Inspect { archive } => inspect_archive(archive),
+        Commands::Build { input, output, codec, level, password, key_file } => {
+            let password = resolve_password(password, key_file)?;
+            build_archive(input, output, codec.to_codec(), level, password)
+        }
+        Commands::Read { archive, file, password } => read_file(archive, file, password),
+        Commands::Ls { archive, password } => list_archive(archive, password),
+        Commands::Inspect { archive, password } => inspect_archive(archive, password),
+        Commands::Verify { archive, password } => verify_archive(archive, password),
+        #[cfg(feature = "fuse")]
+        Commands::Mount { archive, mountpoint, password } => mount_archive(archive, mountpoint, password),
+        Commands::Append { archive, input, codec, level, password, key_file } => {
+            let password = resolve_password(password, key_file)?;
+            append_archive(archive, input, codec.to_codec(), level, password)
+        }
+        Commands::Remove { archive, file, password } => remove_file_from_archive(archive, file, password),
+        Commands::Search { archive, query, password } => search_archive(archive, query, password),
+    }
+}
+
+/// Resolves the effective archive password from either `--password` or
+/// `--key-file` (whose contents are used as the passphrase).
+fn resolve_password(password: Option<String>, key_file: Option<PathBuf>) -> Result<Option<String>> {
+    if password.is_some() {
+        return Ok(password);
+    }
+    match key_file {
+        Some(path) => {
+            let contents = fs::read_to_string(&path).context("Failed to read key file")?;
+            Ok(Some(contents.trim().to_string()))
+        }
+        None => Ok(None),
+    }
+}
+
+/// MIME types that are already compressed (or otherwise incompressible)
+/// enough that spending CPU re-running zstd over them is wasted effort.
+fn is_precompressed(mime: &str) -> bool {
+    mime.starts_with("image/")
+        || mime.starts_with("video/")
+        || mime.starts_with("audio/")
+        || matches!(
+            mime,
+            "application/zip" | "application/gzip" | "application/x-7z-compressed" | "application/x-rar-compressed" | "application/zstd"
+        )
+}
+
+/// Compresses one file's raw bytes for storage, picking a codec (unless
+/// `forced_codec` overrides it), framing it for seekable access if it's
+/// large, and encrypting the result if `key` is set. Shared by `build_archive`
+/// and `append_archive` so both commands make the same per-file decisions.
+fn compress_file(
+    raw_data: &[u8],
+    mime: &str,
+    forced_codec: Option<Codec>,
+    level: i32,
+    key: Option<&[u8; 32]>,
+) -> Result<(Codec, Vec<u8>, Option<Vec<u8>>)> {
+    let original_size = raw_data.len() as u64;
+    let use_frames = forced_codec != Some(Codec::Store)
+        && !is_precompressed(mime)
+        && original_size > seekable::FRAME_SIZE;
+
+    if use_frames {
+        let codec = forced_codec.unwrap_or(Codec::Zstd);
+        let mut frames = Vec::new();
+        let mut frame_bytes = Vec::new();
+
+        for chunk_start in (0..raw_data.len()).step_by(seekable::FRAME_SIZE as usize) {
+            let chunk_end = (chunk_start + seekable::FRAME_SIZE as usize).min(raw_data.len());
+            let chunk = &raw_data[chunk_start..chunk_end];
+
+            let mut encoded = codec.encode(chunk, level)?;
+            if let Some(key) = key {
+                encoded = wax_core::crypto::encrypt(key, &encoded);
+            }
+
+            frames.push(FrameEntry {
+                uncompressed_offset: chunk_start as u64,
+                uncompressed_length: (chunk_end - chunk_start) as u64,
+                compressed_offset: frame_bytes.len() as u64,
+                compressed_length: encoded.len() as u64,
+            });
+            frame_bytes.extend(encoded);
+        }
+
+        Ok((codec, frame_bytes, Some(seekable::encode_frame_index(&frames))))
+    } else {
+        let (codec, compressed_data) = if let Some(forced) = forced_codec {
+            (forced, forced.encode(raw_data, level)?)
+        } else if is_precompressed(mime) {
+            (Codec::Store, raw_data.to_vec())
+        } else {
+            let zstd_data = Codec::Zstd.encode(raw_data, level)?;
+            if zstd_data.len() as u64 >= original_size {
+                (Codec::Store, raw_data.to_vec())
+            } else {
+                (Codec::Zstd, zstd_data)
+            }
+        };
+
+        let compressed_data = match key {
+            Some(key) => wax_core::crypto::encrypt(key, &compressed_data),
+            None => compressed_data,
+        };
+
+        Ok((codec, compressed_data, None))
+    }
+}
+
+/// Encrypts (if `key` is set) and appends the SQLite index at `temp_db_path`
+/// to `file` at its current position, returning the bytes written.
+fn write_index(file: &mut File, temp_db_path: &std::path::Path, key: Option<&[u8; 32]>) -> Result<u64> {
+    let mut index_bytes = fs::read(temp_db_path)?;
+    if let Some(key) = key {
+        index_bytes = wax_core::crypto::encrypt(key, &index_bytes);
     }
+    file.write_all(&index_bytes)?;
+    Ok(index_bytes.len() as u64)
 }
 
-fn build_archive(input: PathBuf, output: PathBuf) -> Result<()> {
+fn build_archive(
+    input: PathBuf,
+    output: PathBuf,
+    forced_codec: Option<Codec>,
+    level: i32,
+    password: Option<String>,
+) -> Result<()> {
     println!("Init: {:?}", output);
 
+    // When a password is set, every blob and the index itself are encrypted
+    // under a key derived from it; the salt travels in the header.
+    let encryption = password.as_deref().map(|p| {
+        let salt = wax_core::crypto::random_salt();
+        let key = wax_core::crypto::derive_key(p, &salt);
+        (key, salt)
+    });
+
     let mut output_file = File::create(&output).context("Failed to create output file")?;
     output_file.write_all(&[0u8; 64])?; 
 
-    let temp_db_path = output.with_extension("db.temp");
-    if temp_db_path.exists() { fs::remove_file(&temp_db_path)?; }
-    
-    let conn = Connection::open(&temp_db_path)?;
+    // A self-deleting scratch file: the index is built here in the clear
+    // (SQLite can't write directly into the encrypted region) and must not
+    // survive on disk if something fails partway through, since it leaks
+    // paths/sizes the same way the finished archive's index doesn't.
+    let temp_index = NamedTempFile::new()?;
+    let temp_db_path = temp_index.path();
+
+    let conn = Connection::open(temp_db_path)?;
     conn.execute(
         "CREATE TABLE files (
             id INTEGER PRIMARY KEY,
@@ -72,10 +309,25 @@ fn build_archive(input: PathBuf, output: PathBuf) -> Result<()> {
             mime_type TEXT,
             blob_offset INTEGER,
             blob_length INTEGER,
-            original_size INTEGER
+            original_size INTEGER,
+            content_hash BLOB,
+            compression_type INTEGER,
+            compression_level INTEGER,
+            checksum INTEGER,
+            frame_index BLOB
         )",
         [],
     )?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_files_path ON files(path)", [])?;
+    conn.execute(
+        "CREATE TABLE metadata (
+            file_id INTEGER NOT NULL,
+            key TEXT NOT NULL,
+            value TEXT
+        )",
+        [],
+    )?;
+    conn.execute("CREATE VIRTUAL TABLE files_fts USING fts5(path, body)", [])?;
 
     println!("Scanning files...");
     let mut files_to_process = Vec::new();
@@ -93,12 +345,24 @@ fn build_archive(input: PathBuf, output: PathBuf) -> Result<()> {
         .progress_chars("#>-"));
 
     let mut stmt = conn.prepare(
-        "INSERT INTO files (path, mime_type, blob_offset, blob_length, original_size) 
-         VALUES (?1, ?2, ?3, ?4, ?5)"
+        "INSERT INTO files (path, mime_type, blob_offset, blob_length, original_size, content_hash, compression_type, compression_level, checksum, frame_index)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)"
     )?;
+    let mut meta_stmt = conn.prepare("INSERT INTO metadata (file_id, key, value) VALUES (?1, ?2, ?3)")?;
+    let mut fts_stmt = conn.prepare("INSERT INTO files_fts (rowid, path, body) VALUES (?1, ?2, ?3)")?;
 
     let mut current_offset = 64u64;
 
+    // Digest -> (blob_offset, blob_length, original_size, codec, checksum,
+    // frame_index), so identical file contents only ever get compressed and
+    // stored once.
+    let mut blobs_by_hash: HashMap<[u8; 32], (u64, u64, u64, u8, u32, Option<Vec<u8>>)> = HashMap::new();
+    // Digest -> extracted text/fields, so re-extracting identical content for
+    // a second path is skipped just like its recompression.
+    let mut extracted_by_hash: HashMap<[u8; 32], wax_core::extract::Extracted> = HashMap::new();
+    let mut bytes_saved = 0u64;
+    let mut files_deduped = 0u64;
+
     for path in files_to_process {
         let relative_path = path.strip_prefix(&input)?.to_string_lossy().to_string();
         let normalized_path = relative_path.replace("\\", "/");
@@ -106,53 +370,96 @@ fn build_archive(input: PathBuf, output: PathBuf) -> Result<()> {
         let mime = mime_guess::from_path(&path).first_or_octet_stream();
         let raw_data = fs::read(&path)?;
         let original_size = raw_data.len() as u64;
+        let digest: [u8; 32] = blake3::hash(&raw_data).into();
 
-        let compressed_data = zstd::stream::encode_all(&raw_data[..], 3)?;
-        let blob_length = compressed_data.len() as u64;
+        let (blob_offset, blob_length, codec, checksum, frame_index) =
+            if let Some((offset, length, _, codec_byte, checksum, frame_index)) = blobs_by_hash.get(&digest) {
+                bytes_saved += original_size;
+                files_deduped += 1;
+                (*offset, *length, Codec::from_u8(*codec_byte).unwrap_or(Codec::Store), *checksum, frame_index.clone())
+            } else {
+                let key = encryption.as_ref().map(|(key, _)| key);
+                let (codec, compressed_data, frame_index) =
+                    compress_file(&raw_data, mime.as_ref(), forced_codec, level, key)?;
+
+                let blob_length = compressed_data.len() as u64;
+                let checksum = crc32fast::hash(&compressed_data);
+                output_file.write_all(&compressed_data)?;
 
-        output_file.write_all(&compressed_data)?;
+                let offset = current_offset;
+                blobs_by_hash.insert(digest, (offset, blob_length, original_size, codec as u8, checksum, frame_index.clone()));
+                current_offset += blob_length;
+                (offset, blob_length, codec, checksum, frame_index)
+            };
 
         stmt.execute((
-            normalized_path,
+            normalized_path.clone(),
             mime.as_ref(),
-            current_offset,
+            blob_offset,
             blob_length,
             original_size,
+            digest.as_slice(),
+            codec as u8,
+            level,
+            checksum,
+            frame_index,
         ))?;
 
-        current_offset += blob_length;
+        let file_id = conn.last_insert_rowid();
+        let extracted = extracted_by_hash
+            .entry(digest)
+            .or_insert_with(|| wax_core::extract::extract(mime.as_ref(), &raw_data))
+            .clone();
+
+        for (key, value) in &extracted.fields {
+            meta_stmt.execute((file_id, key, value))?;
+        }
+        if let Some(text) = &extracted.text {
+            fts_stmt.execute((file_id, &normalized_path, text))?;
+        }
+
         bar.inc(1);
     }
     bar.finish_with_message("Compression Complete");
     drop(stmt);
+    drop(meta_stmt);
+    drop(fts_stmt);
+
+    if files_deduped > 0 {
+        println!(
+            "Deduplicated {} file(s), saved {} bytes of compression work",
+            files_deduped, bytes_saved
+        );
+    }
 
     println!("Finalizing Index...");
     let index_start_offset = current_offset;
     conn.close().map_err(|(_, e)| e)?;
 
-    let mut db_file = File::open(&temp_db_path)?;
-    let index_length = std::io::copy(&mut db_file, &mut output_file)?;
+    let index_length = write_index(&mut output_file, temp_db_path, encryption.as_ref().map(|(key, _)| key))?;
 
     output_file.seek(SeekFrom::Start(0))?;
     let header = WaxHeader {
         magic: WAX_MAGIC,
         version: 1,
-        uuid: [0; 16],
+        uuid: encryption.as_ref().map(|(_, salt)| *salt).unwrap_or([0; 16]),
         index_offset: index_start_offset,
-        index_length: index_length,
+        index_length,
         compression_type: 1,
-        padding: [0; 23],
+        flags: if encryption.is_some() { wax_core::ENCRYPTED_FLAG } else { 0 },
+        padding: [0; 22],
     };
 
     output_file.write_all(header.as_bytes())?;
-    fs::remove_file(temp_db_path)?;
-    
+    // temp_index's Drop removes the plaintext scratch index here (and on any
+    // earlier `?` exit above).
+
     println!("Success! Archive Ready.");
     Ok(())
 }
 
-fn read_file(archive: PathBuf, file_path: String) -> Result<()> {
-    let mut reader = WaxReader::open(&archive)?;
+fn read_file(archive: PathBuf, file_path: String, password: Option<String>) -> Result<()> {
+    let mut reader = WaxReader::open(&archive, password.as_deref())?;
     
     match reader.get_file_data(&file_path) {
         Ok(data) => {
@@ -170,8 +477,8 @@ fn read_file(archive: PathBuf, file_path: String) -> Result<()> {
     Ok(())
 }
 
-fn list_archive(archive: PathBuf) -> Result<()> {
-    let reader = WaxReader::open(&archive)?;
+fn list_archive(archive: PathBuf, password: Option<String>) -> Result<()> {
+    let reader = WaxReader::open(&archive, password.as_deref())?;
     let files = reader.list_files()?;
     
     println!("{:<50} | {:<20} | {:<10}", "PATH", "MIME", "SIZE");
@@ -183,9 +490,434 @@ fn list_archive(archive: PathBuf) -> Result<()> {
     Ok(())
 }
 
-fn inspect_archive(archive: PathBuf) -> Result<()> {
+fn search_archive(archive: PathBuf, query: String, password: Option<String>) -> Result<()> {
+    let reader = WaxReader::open(&archive, password.as_deref())?;
+    let matches = reader.search(&query)?;
+
+    println!("{:<50} | {:<20} | {:<10}", "PATH", "MIME", "SIZE");
+    println!("{:-<50}-|-{:-<20}-|-{:-<10}", "", "", "");
+
+    for entry in &matches {
+        println!("{:<50} | {:<20} | {:<10}", entry.path, entry.mime_type, entry.size);
+    }
+    println!("\n{} match(es)", matches.len());
+    Ok(())
+}
+
+fn inspect_archive(archive: PathBuf, password: Option<String>) -> Result<()> {
     // Just opening it is a validity check
-    let _reader = WaxReader::open(&archive)?;
+    let _reader = WaxReader::open(&archive, password.as_deref())?;
     println!("Status: VALID WAX Archive");
     Ok(())
+}
+
+fn verify_archive(archive: PathBuf, password: Option<String>) -> Result<()> {
+    // SQLite reads the index lazily, so it is `verify_all`'s query against
+    // the `files` table that proves the index_offset/index_length region
+    // parses as a valid SQLite database; "Index: OK" is printed only after it.
+    let mut reader = WaxReader::open(&archive, password.as_deref())?;
+    let report = reader.verify_all()?;
+    println!("Index: OK");
+    println!("Checksums: {} good, {} bad", report.good, report.bad.len());
+
+    if report.bad.is_empty() {
+        println!("Status: VALID WAX Archive");
+        Ok(())
+    } else {
+        for path in &report.bad {
+            println!("CORRUPT: {}", path);
+        }
+        anyhow::bail!("{} blob(s) failed checksum verification", report.bad.len());
+    }
+}
+
+#[cfg(feature = "fuse")]
+fn mount_archive(archive: PathBuf, mountpoint: PathBuf, password: Option<String>) -> Result<()> {
+    let reader = WaxReader::open(&archive, password.as_deref())?;
+    let fs = mount::WaxFilesystem::new(reader)?;
+
+    println!("Mounting {:?} at {:?} (read-only)", archive, mountpoint);
+    let options = vec![fuser::MountOption::RO, fuser::MountOption::FSName("wax".to_string())];
+    fuser::mount2(fs, &mountpoint, &options).context("Failed to mount archive")?;
+    Ok(())
+}
+
+/// Reads an archive's header and, if necessary, derives its encryption key
+/// from `password`. Shared by `append_archive` and `remove_file_from_archive`,
+/// which both need to reopen an existing archive for in-place editing.
+fn open_for_edit(file: &mut File, password: Option<&str>) -> Result<(WaxHeader, Option<[u8; 32]>)> {
+    let mut header_buffer = [0u8; 64];
+    file.read_exact(&mut header_buffer)?;
+
+    let header = WaxHeader::read_from(&header_buffer[..]).context("Header too short")?;
+    if header.magic != WAX_MAGIC {
+        anyhow::bail!("Not a valid WAX archive");
+    }
+
+    let key = if header.flags & wax_core::ENCRYPTED_FLAG != 0 {
+        let password = password.context("Archive is encrypted; --password is required")?;
+        Some(wax_core::crypto::derive_key(password, &header.uuid))
+    } else {
+        None
+    };
+
+    Ok((header, key))
+}
+
+/// Extracts the archive's index to a self-deleting scratch SQLite file so it
+/// can be upserted/deleted against directly, returning both the opened
+/// connection and the scratch file (whose `Drop` removes the decrypted
+/// index from disk, including if an `?` exits early before the caller's
+/// normal cleanup runs).
+fn extract_index_for_edit(
+    file: &mut File,
+    header: &WaxHeader,
+    key: Option<&[u8; 32]>,
+) -> Result<(NamedTempFile, Connection)> {
+    file.seek(SeekFrom::Start(header.index_offset))?;
+    let mut index_bytes = vec![0u8; header.index_length as usize];
+    file.read_exact(&mut index_bytes)?;
+    if let Some(key) = key {
+        index_bytes = wax_core::crypto::decrypt(key, &index_bytes).map_err(|e| anyhow::anyhow!(e.to_string()))?;
+    }
+
+    let mut temp_index = NamedTempFile::new()?;
+    temp_index.write_all(&index_bytes)?;
+    let conn = Connection::open(temp_index.path())?;
+    conn.execute("CREATE UNIQUE INDEX IF NOT EXISTS idx_files_path ON files(path)", [])?;
+    Ok((temp_index, conn))
+}
+
+/// Rewrites the header/index at the current tail of an archive being edited
+/// in place, after `new_index_offset` bytes of payload have been written.
+fn finalize_edit(
+    file: &mut File,
+    header: &WaxHeader,
+    new_index_offset: u64,
+    temp_db_path: &std::path::Path,
+    key: Option<&[u8; 32]>,
+) -> Result<()> {
+    file.seek(SeekFrom::Start(new_index_offset))?;
+    let index_length = write_index(file, temp_db_path, key)?;
+
+    file.seek(SeekFrom::Start(0))?;
+    let new_header = WaxHeader {
+        index_offset: new_index_offset,
+        index_length,
+        ..*header
+    };
+    file.write_all(new_header.as_bytes())?;
+    file.set_len(new_index_offset + index_length)?;
+
+    Ok(())
+}
+
+fn append_archive(
+    archive: PathBuf,
+    input: PathBuf,
+    forced_codec: Option<Codec>,
+    level: i32,
+    password: Option<String>,
+) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&archive)
+        .context("Failed to open archive for appending")?;
+
+    let (header, key) = open_for_edit(&mut file, password.as_deref())?;
+    if password.is_some() && key.is_none() {
+        anyhow::bail!(
+            "{:?} isn't encrypted; --password/--key-file would be silently ignored and new blobs \
+             would land in the archive as plaintext. Rebuild with `build --password` to encrypt it first.",
+            archive
+        );
+    }
+
+    let (temp_index, conn) = extract_index_for_edit(&mut file, &header, key.as_ref())?;
+    let temp_db_path = temp_index.path();
+
+    // Digest -> (blob_offset, blob_length, codec, checksum, frame_index), so
+    // re-appending a file whose contents already exist (in this archive, from
+    // an earlier build or append) is a no-op beyond the index upsert.
+    let mut blobs_by_hash: HashMap<Vec<u8>, (u64, u64, u8, u32, Option<Vec<u8>>)> = HashMap::new();
+    {
+        let mut stmt = conn.prepare(
+            "SELECT content_hash, blob_offset, blob_length, compression_type, checksum, frame_index
+             FROM files WHERE content_hash IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })?;
+        for row in rows {
+            let (hash, offset, length, codec, checksum, frame_index) = row?;
+            blobs_by_hash.insert(hash, (offset, length, codec, checksum, frame_index));
+        }
+    }
+
+    let mut files_to_process = Vec::new();
+    for entry in WalkDir::new(&input) {
+        let entry = entry?;
+        if entry.path().is_file() {
+            files_to_process.push(entry.into_path());
+        }
+    }
+    println!("Appending {} file(s) to {:?}...", files_to_process.len(), archive);
+
+    let mut upsert = conn.prepare(
+        "INSERT INTO files (path, mime_type, blob_offset, blob_length, original_size, content_hash, compression_type, compression_level, checksum, frame_index)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+         ON CONFLICT(path) DO UPDATE SET
+            mime_type = excluded.mime_type,
+            blob_offset = excluded.blob_offset,
+            blob_length = excluded.blob_length,
+            original_size = excluded.original_size,
+            content_hash = excluded.content_hash,
+            compression_type = excluded.compression_type,
+            compression_level = excluded.compression_level,
+            checksum = excluded.checksum,
+            frame_index = excluded.frame_index"
+    )?;
+
+    // New blobs start where the old index used to live; the index is
+    // rewritten at the new tail once every file has been appended.
+    file.seek(SeekFrom::Start(header.index_offset))?;
+    let mut current_offset = header.index_offset;
+    let mut files_added = 0u64;
+    let mut files_unchanged = 0u64;
+
+    for path in files_to_process {
+        let relative_path = path.strip_prefix(&input)?.to_string_lossy().to_string();
+        let normalized_path = relative_path.replace("\\", "/");
+
+        let mime = mime_guess::from_path(&path).first_or_octet_stream();
+        let raw_data = fs::read(&path)?;
+        let original_size = raw_data.len() as u64;
+        let digest: [u8; 32] = blake3::hash(&raw_data).into();
+
+        let (blob_offset, blob_length, codec_byte, checksum, frame_index) =
+            if let Some((offset, length, codec_byte, checksum, frame_index)) = blobs_by_hash.get(digest.as_slice()) {
+                files_unchanged += 1;
+                (*offset, *length, *codec_byte, *checksum, frame_index.clone())
+            } else {
+                let (codec, compressed_data, frame_index) =
+                    compress_file(&raw_data, mime.as_ref(), forced_codec, level, key.as_ref())?;
+
+                let blob_length = compressed_data.len() as u64;
+                let checksum = crc32fast::hash(&compressed_data);
+                file.write_all(&compressed_data)?;
+
+                let offset = current_offset;
+                current_offset += blob_length;
+                files_added += 1;
+
+                blobs_by_hash.insert(digest.to_vec(), (offset, blob_length, codec as u8, checksum, frame_index.clone()));
+                (offset, blob_length, codec as u8, checksum, frame_index)
+            };
+
+        upsert.execute((
+            normalized_path,
+            mime.as_ref(),
+            blob_offset,
+            blob_length,
+            original_size,
+            digest.as_slice(),
+            codec_byte,
+            level,
+            checksum,
+            frame_index,
+        ))?;
+    }
+    drop(upsert);
+
+    println!("Added {} new file(s), {} unchanged (deduped)", files_added, files_unchanged);
+
+    let new_index_offset = current_offset;
+    conn.close().map_err(|(_, e)| e)?;
+    finalize_edit(&mut file, &header, new_index_offset, temp_db_path, key.as_ref())?;
+
+    println!("Success! Archive updated.");
+    Ok(())
+}
+
+fn remove_file_from_archive(archive: PathBuf, file_path: String, password: Option<String>) -> Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&archive)
+        .context("Failed to open archive for editing")?;
+
+    let (header, key) = open_for_edit(&mut file, password.as_deref())?;
+
+    let (temp_index, conn) = extract_index_for_edit(&mut file, &header, key.as_ref())?;
+
+    let removed = conn.execute("DELETE FROM files WHERE path = ?1", [&file_path])?;
+    if removed == 0 {
+        conn.close().map_err(|(_, e)| e)?;
+        anyhow::bail!("File not found in archive: {}", file_path);
+    }
+
+    // The removed blob's bytes are left in place (orphaned); only the index
+    // shrinks, so this stays an O(index-size) operation rather than a full
+    // rewrite of the payload region.
+    let index_offset = header.index_offset;
+    conn.close().map_err(|(_, e)| e)?;
+    finalize_edit(&mut file, &header, index_offset, temp_index.path(), key.as_ref())?;
+
+    println!("Removed {} from archive.", file_path);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A scratch directory under the system temp dir, unique to this test
+    /// process, cleaned up when the guard drops.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("wax-builder-test-{label}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self) -> &std::path::Path {
+            &self.0
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn build_append_remove_round_trips_and_verifies_clean() {
+        let work = TempDir::new("append-remove");
+
+        let input_dir = work.path().join("input");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(input_dir.join("a.txt"), b"hello wax").unwrap();
+
+        let archive_path = work.path().join("out.wax");
+        build_archive(input_dir, archive_path.clone(), None, 3, None).unwrap();
+
+        let more_dir = work.path().join("more");
+        fs::create_dir_all(&more_dir).unwrap();
+        fs::write(more_dir.join("b.txt"), b"a second file").unwrap();
+        append_archive(archive_path.clone(), more_dir, None, 3, None).unwrap();
+
+        let mut reader = WaxReader::open(&archive_path, None).unwrap();
+        assert_eq!(reader.get_file_data("a.txt").unwrap(), b"hello wax");
+        assert_eq!(reader.get_file_data("b.txt").unwrap(), b"a second file");
+        assert!(reader.verify_all().unwrap().bad.is_empty());
+        drop(reader);
+
+        remove_file_from_archive(archive_path.clone(), "a.txt".to_string(), None).unwrap();
+
+        let mut reader = WaxReader::open(&archive_path, None).unwrap();
+        assert!(reader.get_file_data("a.txt").is_err());
+        assert_eq!(reader.get_file_data("b.txt").unwrap(), b"a second file");
+        assert!(reader.verify_all().unwrap().bad.is_empty());
+    }
+
+    #[test]
+    fn search_finds_a_file_by_its_extracted_text_body() {
+        let work = TempDir::new("search");
+
+        let input_dir = work.path().join("input");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(input_dir.join("notes.txt"), b"the quick brown fox jumps over the lazy dog").unwrap();
+        fs::write(input_dir.join("other.txt"), b"completely unrelated content").unwrap();
+
+        let archive_path = work.path().join("out.wax");
+        build_archive(input_dir, archive_path.clone(), None, 3, None).unwrap();
+
+        let reader = WaxReader::open(&archive_path, None).unwrap();
+        let matches = reader.search("fox").unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path, "notes.txt");
+    }
+
+    #[test]
+    fn verify_detects_a_corrupted_blob() {
+        let work = TempDir::new("verify-corruption");
+
+        let input_dir = work.path().join("input");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(input_dir.join("a.txt"), b"bytes that must not bit-rot").unwrap();
+
+        let archive_path = work.path().join("out.wax");
+        build_archive(input_dir, archive_path.clone(), Some(Codec::Store), 3, None).unwrap();
+
+        // Flip a byte just past the 64-byte header, inside the first (and
+        // only) blob, without touching the index.
+        let mut file = fs::OpenOptions::new().read(true).write(true).open(&archive_path).unwrap();
+        file.seek(SeekFrom::Start(64)).unwrap();
+        let mut byte = [0u8; 1];
+        file.read_exact(&mut byte).unwrap();
+        file.seek(SeekFrom::Start(64)).unwrap();
+        file.write_all(&[byte[0] ^ 0xff]).unwrap();
+        drop(file);
+
+        let mut reader = WaxReader::open(&archive_path, None).unwrap();
+        let report = reader.verify_all().unwrap();
+        assert_eq!(report.bad, vec!["a.txt".to_string()]);
+
+        assert!(matches!(
+            reader.get_file_data("a.txt"),
+            Err(wax_core::reader::WaxError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn identical_file_contents_are_deduped_to_one_blob() {
+        let work = TempDir::new("dedup");
+
+        let input_dir = work.path().join("input");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(input_dir.join("a.txt"), b"duplicate payload").unwrap();
+        fs::write(input_dir.join("b.txt"), b"duplicate payload").unwrap();
+
+        let archive_path = work.path().join("out.wax");
+        build_archive(input_dir, archive_path.clone(), None, 3, None).unwrap();
+
+        let reader = WaxReader::open(&archive_path, None).unwrap();
+        assert_eq!(reader.get_blob_offset("a.txt").unwrap(), reader.get_blob_offset("b.txt").unwrap());
+        assert_eq!(
+            reader.get_content_hash("a.txt").unwrap(),
+            reader.get_content_hash("b.txt").unwrap()
+        );
+    }
+
+    #[test]
+    fn append_with_password_onto_unencrypted_archive_is_rejected() {
+        let work = TempDir::new("append-password-guard");
+
+        let input_dir = work.path().join("input");
+        fs::create_dir_all(&input_dir).unwrap();
+        fs::write(input_dir.join("a.txt"), b"hello wax").unwrap();
+
+        let archive_path = work.path().join("out.wax");
+        build_archive(input_dir, archive_path.clone(), None, 3, None).unwrap();
+
+        let more_dir = work.path().join("more");
+        fs::create_dir_all(&more_dir).unwrap();
+        fs::write(more_dir.join("b.txt"), b"second").unwrap();
+
+        let err = append_archive(archive_path, more_dir, None, 3, Some("s3cret".to_string())).unwrap_err();
+        assert!(err.to_string().contains("isn't encrypted"));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,262 @@
+//! Read-only FUSE view of a `.wax` archive (behind the `fuse` cargo feature).
+
+use fuser::{FileAttr, FileType, Filesystem, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry, Request};
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::time::{Duration, SystemTime};
+use wax_core::reader::WaxReader;
+
+const TTL: Duration = Duration::from_secs(1);
+const ROOT_INO: u64 = 1;
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Dir { children: Vec<u64> },
+    File { path: String, size: u64 },
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    ino: u64,
+    name: String,
+    kind: NodeKind,
+}
+
+/// A `fuser::Filesystem` backed by a `WaxReader`. The directory tree is built
+/// once up front from the index's file paths; reads decompress on demand.
+pub struct WaxFilesystem {
+    reader: WaxReader,
+    nodes: HashMap<u64, Node>,
+}
+
+impl WaxFilesystem {
+    pub fn new(reader: WaxReader) -> anyhow::Result<Self> {
+        let mut nodes = HashMap::new();
+        nodes.insert(
+            ROOT_INO,
+            Node {
+                ino: ROOT_INO,
+                name: String::new(),
+                kind: NodeKind::Dir { children: Vec::new() },
+            },
+        );
+
+        let mut next_ino = ROOT_INO + 1;
+        let entries = reader.list_files()?;
+
+        for entry in entries {
+            let mut parent_ino = ROOT_INO;
+            let components: Vec<&str> = entry.path.split('/').filter(|c| !c.is_empty()).collect();
+
+            for (i, component) in components.iter().enumerate() {
+                let is_leaf = i == components.len() - 1;
+
+                let existing = match &nodes[&parent_ino].kind {
+                    NodeKind::Dir { children } => children
+                        .iter()
+                        .copied()
+                        .find(|&child| nodes[&child].name == *component),
+                    NodeKind::File { .. } => None,
+                };
+
+                let child_ino = match existing {
+                    Some(ino) => ino,
+                    None => {
+                        let ino = next_ino;
+                        next_ino += 1;
+
+                        let kind = if is_leaf {
+                            NodeKind::File { path: entry.path.clone(), size: entry.size }
+                        } else {
+                            NodeKind::Dir { children: Vec::new() }
+                        };
+
+                        nodes.insert(
+                            ino,
+                            Node { ino, name: component.to_string(), kind },
+                        );
+
+                        if let NodeKind::Dir { children } = &mut nodes.get_mut(&parent_ino).unwrap().kind {
+                            children.push(ino);
+                        }
+
+                        ino
+                    }
+                };
+
+                parent_ino = child_ino;
+            }
+        }
+
+        Ok(Self { reader, nodes })
+    }
+
+    fn attr_for(&self, node: &Node) -> FileAttr {
+        let (kind, size, perm) = match &node.kind {
+            NodeKind::Dir { .. } => (FileType::Directory, 0, 0o755),
+            NodeKind::File { size, .. } => (FileType::RegularFile, *size, 0o644),
+        };
+
+        FileAttr {
+            ino: node.ino,
+            size,
+            blocks: (size + 511) / 512,
+            atime: SystemTime::UNIX_EPOCH,
+            mtime: SystemTime::UNIX_EPOCH,
+            ctime: SystemTime::UNIX_EPOCH,
+            crtime: SystemTime::UNIX_EPOCH,
+            kind,
+            perm,
+            nlink: 1,
+            uid: 0,
+            gid: 0,
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        }
+    }
+}
+
+impl Filesystem for WaxFilesystem {
+    fn lookup(&mut self, _req: &Request, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let Some(name) = name.to_str() else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+
+        let children = match self.nodes.get(&parent).map(|n| &n.kind) {
+            Some(NodeKind::Dir { children }) => children.clone(),
+            _ => {
+                reply.error(libc::ENOENT);
+                return;
+            }
+        };
+
+        match children.into_iter().find(|ino| self.nodes[ino].name == name) {
+            Some(ino) => reply.entry(&TTL, &self.attr_for(&self.nodes[&ino]), 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request, ino: u64, reply: ReplyAttr) {
+        match self.nodes.get(&ino) {
+            Some(node) => reply.attr(&TTL, &self.attr_for(node)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let path = match self.nodes.get(&ino) {
+            Some(Node { kind: NodeKind::File { path, .. }, .. }) => path.clone(),
+            _ => {
+                reply.error(libc::ENOENT);
+                return;
+            }
+        };
+
+        let offset = offset.max(0) as u64;
+        match self.reader.read_range(&path, offset, size as u64) {
+            Ok(data) => reply.data(&data),
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let children = match self.nodes.get(&ino).map(|n| &n.kind) {
+            Some(NodeKind::Dir { children }) => children.clone(),
+            _ => {
+                reply.error(libc::ENOENT);
+                return;
+            }
+        };
+
+        let mut entries = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (ino, FileType::Directory, "..".to_string()),
+        ];
+        for child_ino in children {
+            let node = &self.nodes[&child_ino];
+            let kind = match node.kind {
+                NodeKind::Dir { .. } => FileType::Directory,
+                NodeKind::File { .. } => FileType::RegularFile,
+            };
+            entries.push((child_ino, kind, node.name.clone()));
+        }
+
+        for (i, (entry_ino, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(entry_ino, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TempDir(std::path::PathBuf);
+
+    impl TempDir {
+        fn new(label: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("wax-mount-test-{label}-{}", std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn node_tree_mirrors_nested_archive_paths() {
+        let work = TempDir::new("tree");
+
+        let input_dir = work.0.join("input");
+        std::fs::create_dir_all(input_dir.join("sub")).unwrap();
+        std::fs::write(input_dir.join("root.txt"), b"top level").unwrap();
+        std::fs::write(input_dir.join("sub/nested.txt"), b"nested").unwrap();
+
+        let archive_path = work.0.join("out.wax");
+        crate::build_archive(input_dir, archive_path.clone(), None, 3, None).unwrap();
+
+        let reader = WaxReader::open(&archive_path, None).unwrap();
+        let fs = WaxFilesystem::new(reader).unwrap();
+
+        let root = &fs.nodes[&ROOT_INO];
+        let NodeKind::Dir { children } = &root.kind else { panic!("root should be a dir") };
+        assert_eq!(children.len(), 2, "expected root.txt and sub/ under the root");
+
+        let sub_ino = children
+            .iter()
+            .find(|ino| fs.nodes[ino].name == "sub")
+            .expect("sub/ directory should exist");
+        let NodeKind::Dir { children: sub_children } = &fs.nodes[sub_ino].kind else {
+            panic!("sub should be a dir")
+        };
+        assert_eq!(sub_children.len(), 1);
+        assert_eq!(fs.nodes[&sub_children[0]].name, "nested.txt");
+    }
+}
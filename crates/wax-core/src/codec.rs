@@ -0,0 +1,90 @@
+use std::io::{Read, Write};
+
+/// The compression scheme a blob was stored under. Persisted per-file in the
+/// index so `WaxReader` can dispatch decompression correctly even when an
+/// archive mixes codecs (e.g. `Store` for already-compressed media next to
+/// `Zstd` for everything else).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Store = 0,
+    Zstd = 1,
+    Xz = 2,
+    Bzip2 = 3,
+}
+
+impl Codec {
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Codec::Store),
+            1 => Some(Codec::Zstd),
+            2 => Some(Codec::Xz),
+            3 => Some(Codec::Bzip2),
+            _ => None,
+        }
+    }
+
+    pub fn encode(self, data: &[u8], level: i32) -> std::io::Result<Vec<u8>> {
+        match self {
+            Codec::Store => Ok(data.to_vec()),
+            Codec::Zstd => zstd::stream::encode_all(data, level),
+            Codec::Xz => {
+                let mut encoder = xz2::write::XzEncoder::new(Vec::new(), level.max(0) as u32);
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+            Codec::Bzip2 => {
+                let mut encoder =
+                    bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::new(level.max(0) as u32));
+                encoder.write_all(data)?;
+                encoder.finish()
+            }
+        }
+    }
+
+    pub fn decode(self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Codec::Store => Ok(data.to_vec()),
+            Codec::Zstd => zstd::stream::decode_all(data),
+            Codec::Xz => {
+                let mut out = Vec::new();
+                xz2::read::XzDecoder::new(data).read_to_end(&mut out)?;
+                Ok(out)
+            }
+            Codec::Bzip2 => {
+                let mut out = Vec::new();
+                bzip2::read::BzDecoder::new(data).read_to_end(&mut out)?;
+                Ok(out)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_u8_round_trips_known_values() {
+        assert_eq!(Codec::from_u8(0), Some(Codec::Store));
+        assert_eq!(Codec::from_u8(1), Some(Codec::Zstd));
+        assert_eq!(Codec::from_u8(2), Some(Codec::Xz));
+        assert_eq!(Codec::from_u8(3), Some(Codec::Bzip2));
+        assert_eq!(Codec::from_u8(4), None);
+    }
+
+    #[test]
+    fn every_codec_round_trips_its_input() {
+        let data = b"the quick brown fox jumps over the lazy dog".repeat(64);
+        for codec in [Codec::Store, Codec::Zstd, Codec::Xz, Codec::Bzip2] {
+            let encoded = codec.encode(&data, 3).unwrap();
+            let decoded = codec.decode(&encoded).unwrap();
+            assert_eq!(decoded, data, "{codec:?} failed to round-trip");
+        }
+    }
+
+    #[test]
+    fn store_is_a_no_op() {
+        let data = b"raw bytes, unchanged";
+        assert_eq!(Codec::Store.encode(data, 3).unwrap(), data);
+    }
+}
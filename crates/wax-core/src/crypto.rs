@@ -0,0 +1,101 @@
+use crate::reader::WaxError;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use rand::RngCore;
+
+/// Length of the random nonce prefixed to every encrypted blob.
+pub const NONCE_LEN: usize = 12;
+
+/// Derives a 256-bit AES key from a password and the per-archive salt stored
+/// in `WaxHeader.uuid`.
+pub fn derive_key(password: &str, salt: &[u8; 16]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .expect("argon2id key derivation should not fail for a 32-byte output");
+    key
+}
+
+/// Generates a fresh random salt to embed in a new archive's header.
+pub fn random_salt() -> [u8; 16] {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    salt
+}
+
+/// Encrypts `plaintext` under `key` with a random nonce, returning
+/// `nonce || ciphertext || tag`.
+pub fn encrypt(key: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+    let nonce = Nonce::from_slice(&nonce_bytes);
+
+    let ciphertext = cipher
+        .encrypt(nonce, plaintext)
+        .expect("AES-256-GCM encryption should not fail");
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce_bytes);
+    out.extend_from_slice(&ciphertext);
+    out
+}
+
+/// Reverses [`encrypt`], verifying the GCM tag. Any failure (truncated
+/// input, wrong key, tampered bytes) is reported as `WaxError::Decryption`.
+pub fn decrypt(key: &[u8; 32], data: &[u8]) -> Result<Vec<u8>, WaxError> {
+    if data.len() < NONCE_LEN {
+        return Err(WaxError::Decryption);
+    }
+    let (nonce_bytes, ciphertext) = data.split_at(NONCE_LEN);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key));
+    cipher
+        .decrypt(Nonce::from_slice(nonce_bytes), ciphertext)
+        .map_err(|_| WaxError::Decryption)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derive_key_is_deterministic_per_password_and_salt() {
+        let salt = [7u8; 16];
+        assert_eq!(derive_key("hunter2", &salt), derive_key("hunter2", &salt));
+        assert_ne!(derive_key("hunter2", &salt), derive_key("hunter3", &salt));
+        assert_ne!(derive_key("hunter2", &salt), derive_key("hunter2", &[8u8; 16]));
+    }
+
+    #[test]
+    fn encrypt_decrypt_round_trips() {
+        let key = derive_key("correct horse battery staple", &random_salt());
+        let plaintext = b"content that must survive the round trip";
+
+        let ciphertext = encrypt(&key, plaintext);
+        assert_eq!(decrypt(&key, &ciphertext).unwrap(), plaintext);
+    }
+
+    #[test]
+    fn decrypt_fails_with_wrong_key() {
+        let salt = random_salt();
+        let key = derive_key("password-one", &salt);
+        let wrong_key = derive_key("password-two", &salt);
+
+        let ciphertext = encrypt(&key, b"secret data");
+        assert!(matches!(decrypt(&wrong_key, &ciphertext), Err(WaxError::Decryption)));
+    }
+
+    #[test]
+    fn decrypt_fails_on_truncated_or_tampered_input() {
+        let key = derive_key("password", &random_salt());
+        let mut ciphertext = encrypt(&key, b"secret data");
+
+        assert!(matches!(decrypt(&key, &ciphertext[..NONCE_LEN - 1]), Err(WaxError::Decryption)));
+
+        let last = ciphertext.len() - 1;
+        ciphertext[last] ^= 0xff;
+        assert!(matches!(decrypt(&key, &ciphertext), Err(WaxError::Decryption)));
+    }
+}
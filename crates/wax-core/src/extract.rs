@@ -0,0 +1,159 @@
+//! Per-MIME content extraction run during `build_archive`: pulls a
+//! full-text body and a handful of structured fields out of each file so
+//! they can be indexed in the `metadata` table and the `files_fts` FTS5
+//! table, and later searched via [`crate::reader::WaxReader::search`].
+//!
+//! Type-specific extractors beyond plain text (images, audio, HTML) sit
+//! behind their own cargo feature so archives that never need them don't
+//! pull in the extra dependencies.
+
+/// One file's extracted full-text body (indexed into `files_fts`) and
+/// structured key/value fields (indexed into `metadata`).
+#[derive(Debug, Clone, Default)]
+pub struct Extracted {
+    pub text: Option<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+/// Runs the extractor matching `mime` over `raw_data`. Returns an empty
+/// `Extracted` for MIME types with no extractor, or whose extractor's
+/// feature isn't enabled; `text/html` without its feature falls back to the
+/// plain-text extractor instead.
+pub fn extract(mime: &str, raw_data: &[u8]) -> Extracted {
+    if mime.starts_with("text/") || mime == "text/markdown" {
+        #[cfg(feature = "extract-html")]
+        if mime == "text/html" {
+            return html::extract(raw_data);
+        }
+        return extract_plain_text(raw_data);
+    }
+
+    #[cfg(feature = "extract-image")]
+    if mime.starts_with("image/") {
+        return image::extract(raw_data);
+    }
+
+    #[cfg(feature = "extract-audio")]
+    if mime.starts_with("audio/") {
+        return audio::extract(raw_data);
+    }
+
+    Extracted::default()
+}
+
+fn extract_plain_text(raw_data: &[u8]) -> Extracted {
+    match std::str::from_utf8(raw_data) {
+        Ok(text) => Extracted { text: Some(text.to_string()), fields: Vec::new() },
+        Err(_) => Extracted::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_mime_extracts_the_body_verbatim() {
+        let extracted = extract("text/plain", b"hello wax archive");
+        assert_eq!(extracted.text.as_deref(), Some("hello wax archive"));
+        assert!(extracted.fields.is_empty());
+    }
+
+    #[test]
+    fn markdown_mime_is_treated_as_plain_text() {
+        let extracted = extract("text/markdown", b"# heading");
+        assert_eq!(extracted.text.as_deref(), Some("# heading"));
+    }
+
+    #[test]
+    fn invalid_utf8_text_yields_no_body() {
+        let extracted = extract("text/plain", &[0xff, 0xfe, 0xfd]);
+        assert!(extracted.text.is_none());
+        assert!(extracted.fields.is_empty());
+    }
+
+    #[test]
+    fn mime_with_no_extractor_yields_default() {
+        let extracted = extract("application/octet-stream", b"\x00\x01\x02");
+        assert!(extracted.text.is_none());
+        assert!(extracted.fields.is_empty());
+    }
+}
+
+#[cfg(feature = "extract-image")]
+mod image {
+    use super::Extracted;
+
+    /// EXIF dimensions, camera model and capture time, when present.
+    pub fn extract(raw_data: &[u8]) -> Extracted {
+        let mut fields = Vec::new();
+
+        if let Ok(exif) = exif::Reader::new().read_from_container(&mut std::io::Cursor::new(raw_data)) {
+            for field in exif.fields() {
+                let key = match field.tag {
+                    exif::Tag::PixelXDimension => "width",
+                    exif::Tag::PixelYDimension => "height",
+                    exif::Tag::Model => "camera",
+                    exif::Tag::DateTimeOriginal => "taken_at",
+                    _ => continue,
+                };
+                fields.push((key.to_string(), field.display_value().to_string()));
+            }
+        }
+
+        Extracted { text: None, fields }
+    }
+}
+
+#[cfg(feature = "extract-audio")]
+mod audio {
+    use super::Extracted;
+
+    /// ID3 title/artist/album tags, when present.
+    pub fn extract(raw_data: &[u8]) -> Extracted {
+        let mut fields = Vec::new();
+
+        if let Ok(tag) = id3::Tag::read_from(std::io::Cursor::new(raw_data)) {
+            if let Some(title) = tag.title() {
+                fields.push(("title".to_string(), title.to_string()));
+            }
+            if let Some(artist) = tag.artist() {
+                fields.push(("artist".to_string(), artist.to_string()));
+            }
+            if let Some(album) = tag.album() {
+                fields.push(("album".to_string(), album.to_string()));
+            }
+        }
+
+        Extracted { text: None, fields }
+    }
+}
+
+#[cfg(feature = "extract-html")]
+mod html {
+    use super::Extracted;
+
+    /// `<title>` and the `description` meta tag, plus the page's full
+    /// source text, markup included, as the FTS body.
+    pub fn extract(raw_data: &[u8]) -> Extracted {
+        let text = String::from_utf8_lossy(raw_data);
+        let document = scraper::Html::parse_document(&text);
+        let mut fields = Vec::new();
+
+        if let Ok(selector) = scraper::Selector::parse("title") {
+            if let Some(title) = document.select(&selector).next() {
+                fields.push(("title".to_string(), title.text().collect::<String>()));
+            }
+        }
+
+        if let Ok(selector) = scraper::Selector::parse(r#"meta[name="description"]"#) {
+            if let Some(meta) = document.select(&selector).next() {
+                if let Some(content) = meta.value().attr("content") {
+                    fields.push(("description".to_string(), content.to_string()));
+                }
+            }
+        }
+
+        Extracted { text: Some(text.into_owned()), fields }
+    }
+}
@@ -1,20 +1,31 @@
 use zerocopy::{AsBytes, FromBytes, FromZeroes};
 
-pub mod reader; 
+pub mod codec;
+pub mod crypto;
+pub mod extract;
+pub mod reader;
+pub mod seekable;
 
 /// Magic bytes 'WAX1' to identify the file format.
 pub const WAX_MAGIC: [u8; 4] = [0x57, 0x41, 0x58, 0x31];
 
+/// `WaxHeader.flags` bit set when the archive's blobs and index were
+/// encrypted with a password-derived key (see [`crypto`]).
+pub const ENCRYPTED_FLAG: u8 = 0x01;
+
 #[repr(C)]
 #[derive(Debug, Clone, Copy, FromBytes, AsBytes, FromZeroes)]
 pub struct WaxHeader {
     pub magic: [u8; 4],
     pub version: u32,
+    /// All zeroes for an unencrypted archive, or, when `flags & ENCRYPTED_FLAG` is set,
+    /// the 16-byte Argon2id salt used to derive the encryption key.
     pub uuid: [u8; 16],
     pub index_offset: u64,
     pub index_length: u64,
     pub compression_type: u8,
-    pub padding: [u8; 23], 
+    pub flags: u8,
+    pub padding: [u8; 22],
 }
 
 impl Default for WaxHeader {
@@ -26,7 +37,8 @@ impl Default for WaxHeader {
             index_offset: 0,
             index_length: 0,
             compression_type: 1,
-            padding: [0; 23],
+            flags: 0,
+            padding: [0; 22],
         }
     }
 }
@@ -1,7 +1,10 @@
-use crate::{WaxHeader, WAX_MAGIC};
+use crate::codec::Codec;
+use crate::seekable::{self, FrameEntry};
+use crate::{WaxHeader, ENCRYPTED_FLAG, WAX_MAGIC};
 use rusqlite::{Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
+use std::io::{Read, Seek, SeekFrom, Write};
 use std::path::Path;
 use tempfile::NamedTempFile;
 use thiserror::Error;
@@ -17,6 +20,20 @@ pub enum WaxError {
     InvalidMagic,
     #[error("File not found: {0}")]
     FileNotFound(String),
+    #[error("Unknown codec byte: {0}")]
+    UnknownCodec(u8),
+    #[error("Decryption failed: wrong password or corrupted archive")]
+    Decryption,
+    #[error("Checksum mismatch for {path}: blob is corrupted")]
+    ChecksumMismatch { path: String },
+}
+
+/// Outcome of [`WaxReader::verify_all`]: how many blobs matched their stored
+/// CRC32 and which paths didn't.
+#[derive(Debug)]
+pub struct VerifyReport {
+    pub good: u64,
+    pub bad: Vec<String>,
 }
 
 // A simple struct to return file metadata
@@ -25,22 +42,35 @@ pub struct WaxEntry {
     pub path: String,
     pub mime_type: String,
     pub size: u64,
+    pub content_hash: Option<Vec<u8>>,
 }
 
 pub struct WaxReader {
     archive_file: File,
     index_conn: Connection,
     _temp_index: NamedTempFile,
+    key: Option<[u8; 32]>,
+    // blob_offsets whose CRC32 has already been checked against the stored
+    // checksum this session, so read_range doesn't re-read the whole blob on
+    // every call into a file it's already streaming.
+    verified_blobs: HashSet<u64>,
+    // Decoded (decrypted + decompressed) bytes of single-shot (non-framed)
+    // blobs, keyed by blob_offset, so repeated small reads of the same file
+    // (e.g. sequential FUSE page reads) don't redecompress it on every call.
+    // Framed blobs are deliberately not cached here, since `read_range`
+    // decodes only the frames a caller asks for and the whole point is to
+    // avoid materializing a large file in memory.
+    decoded_blobs: HashMap<u64, Vec<u8>>,
 }
 
 impl WaxReader {
-    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, WaxError> {
+    pub fn open<P: AsRef<Path>>(path: P, password: Option<&str>) -> Result<Self, WaxError> {
         let mut file = File::open(path)?;
 
         // Read Header
         let mut header_buffer = [0u8; 64];
         file.read_exact(&mut header_buffer)?;
-        
+
         let header = WaxHeader::read_from(&header_buffer[..])
             .ok_or(std::io::Error::new(std::io::ErrorKind::InvalidData, "Header too short"))?;
 
@@ -48,12 +78,24 @@ impl WaxReader {
             return Err(WaxError::InvalidMagic);
         }
 
-        // Extract Index
+        let key = if header.flags & ENCRYPTED_FLAG != 0 {
+            let password = password.ok_or(WaxError::Decryption)?;
+            Some(crate::crypto::derive_key(password, &header.uuid))
+        } else {
+            None
+        };
+
+        // Extract Index (decrypting it first if the archive is encrypted)
         file.seek(SeekFrom::Start(header.index_offset))?;
-        
+
+        let mut index_bytes = vec![0u8; header.index_length as usize];
+        file.read_exact(&mut index_bytes)?;
+        if let Some(key) = &key {
+            index_bytes = crate::crypto::decrypt(key, &index_bytes)?;
+        }
+
         let mut temp_index = NamedTempFile::new()?;
-        let mut index_reader = file.try_clone()?.take(header.index_length);
-        std::io::copy(&mut index_reader, &mut temp_index)?;
+        temp_index.write_all(&index_bytes)?;
 
         let conn = Connection::open(temp_index.path())?;
 
@@ -61,33 +103,207 @@ impl WaxReader {
             archive_file: file,
             index_conn: conn,
             _temp_index: temp_index,
+            key,
+            verified_blobs: HashSet::new(),
+            decoded_blobs: HashMap::new(),
         })
     }
 
+    /// Verifies `path`'s blob against its stored CRC32 unless it's already
+    /// been checked this session, caching the result by `blob_offset` (blobs
+    /// can be shared by several paths after dedup).
+    fn ensure_checksum(
+        &mut self,
+        path: &str,
+        blob_offset: u64,
+        blob_length: u64,
+        expected_checksum: u32,
+    ) -> Result<(), WaxError> {
+        if self.verified_blobs.contains(&blob_offset) {
+            return Ok(());
+        }
+
+        self.archive_file.seek(SeekFrom::Start(blob_offset))?;
+        let mut buffer = vec![0u8; blob_length as usize];
+        self.archive_file.read_exact(&mut buffer)?;
+
+        if crc32fast::hash(&buffer) != expected_checksum {
+            return Err(WaxError::ChecksumMismatch { path: path.to_string() });
+        }
+
+        self.verified_blobs.insert(blob_offset);
+        Ok(())
+    }
+
     pub fn get_file_data(&mut self, path: &str) -> Result<Vec<u8>, WaxError> {
         let mut stmt = self.index_conn.prepare(
-            "SELECT blob_offset, blob_length FROM files WHERE path = ?1"
+            "SELECT blob_offset, blob_length, compression_type, checksum, frame_index FROM files WHERE path = ?1"
         )?;
 
-        let result: Option<(u64, u64)> = stmt.query_row([path], |row| {
-            let off: u64 = row.get(0)?;
-            let len: u64 = row.get(1)?;
-            Ok((off, len))
+        let result: Option<(u64, u64, u8, u32, Option<Vec<u8>>)> = stmt.query_row([path], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?))
         }).optional()?;
 
-        let (offset, length) = match result {
+        let (offset, length, codec_byte, expected_checksum, frame_index) = match result {
             Some(r) => r,
             None => return Err(WaxError::FileNotFound(path.to_string())),
         };
 
+        let codec = Codec::from_u8(codec_byte).ok_or(WaxError::UnknownCodec(codec_byte))?;
+        let is_framed = frame_index.as_ref().is_some_and(|bytes| !bytes.is_empty());
+
+        // Single-shot blobs already decoded this session skip the disk read
+        // and checksum redo entirely; their checksum was already verified
+        // when they were first cached below.
+        if !is_framed {
+            if let Some(cached) = self.decoded_blobs.get(&offset) {
+                return Ok(cached.clone());
+            }
+        }
+
         self.archive_file.seek(SeekFrom::Start(offset))?;
 
-        let mut compressed_buffer = vec![0u8; length as usize];
-        self.archive_file.read_exact(&mut compressed_buffer)?;
+        let mut blob_buffer = vec![0u8; length as usize];
+        self.archive_file.read_exact(&mut blob_buffer)?;
+
+        if crc32fast::hash(&blob_buffer) != expected_checksum {
+            return Err(WaxError::ChecksumMismatch { path: path.to_string() });
+        }
+        self.verified_blobs.insert(offset);
+
+        match frame_index.filter(|bytes| !bytes.is_empty()) {
+            Some(index_bytes) => {
+                let frames = seekable::decode_frame_index(&index_bytes);
+                let mut out = Vec::new();
+                for frame in frames {
+                    let start = frame.compressed_offset as usize;
+                    let end = start + frame.compressed_length as usize;
+                    out.extend(self.decode_payload(&blob_buffer[start..end], codec)?);
+                }
+                Ok(out)
+            }
+            None => {
+                let data = self.decode_payload(&blob_buffer, codec)?;
+                self.decoded_blobs.insert(offset, data.clone());
+                Ok(data)
+            }
+        }
+    }
+
+    /// Decompresses (and, if the archive is encrypted, decrypts) a single
+    /// stored payload: either a whole single-shot blob or one seekable frame.
+    fn decode_payload(&self, data: &[u8], codec: Codec) -> Result<Vec<u8>, WaxError> {
+        let payload = match &self.key {
+            Some(key) => crate::crypto::decrypt(key, data)?,
+            None => data.to_vec(),
+        };
+        Ok(codec.decode(&payload)?)
+    }
+
+    /// Reads `len` uncompressed bytes starting at `offset` without
+    /// materializing the whole file. For seekable (frame-indexed) blobs this
+    /// decompresses only the frames overlapping the window; everything else
+    /// falls back to decompressing the full blob via [`Self::get_file_data`].
+    pub fn read_range(&mut self, path: &str, offset: u64, len: u64) -> Result<Vec<u8>, WaxError> {
+        let mut stmt = self.index_conn.prepare(
+            "SELECT blob_offset, blob_length, compression_type, checksum, frame_index, original_size FROM files WHERE path = ?1"
+        )?;
+
+        let result: Option<(u64, u64, u8, u32, Option<Vec<u8>>, u64)> = stmt.query_row([path], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?))
+        }).optional()?;
+
+        let (blob_offset, blob_length, codec_byte, checksum, frame_index, original_size) = match result {
+            Some(r) => r,
+            None => return Err(WaxError::FileNotFound(path.to_string())),
+        };
+
+        let codec = Codec::from_u8(codec_byte).ok_or(WaxError::UnknownCodec(codec_byte))?;
+        let end = offset.saturating_add(len).min(original_size);
+        if offset >= end {
+            return Ok(Vec::new());
+        }
+
+        let frames: Vec<FrameEntry> = match frame_index.filter(|bytes| !bytes.is_empty()) {
+            Some(bytes) => seekable::decode_frame_index(&bytes),
+            None => {
+                // get_file_data validates the checksum itself.
+                let data = self.get_file_data(path)?;
+                let end = end.min(data.len() as u64) as usize;
+                return Ok(data[offset as usize..end].to_vec());
+            }
+        };
+
+        self.ensure_checksum(path, blob_offset, blob_length, checksum)?;
+
+        let covering = seekable::frames_covering(&frames, offset, end - offset);
+        let mut window = Vec::new();
+        let mut window_start = None;
+
+        for frame in covering {
+            self.archive_file.seek(SeekFrom::Start(blob_offset + frame.compressed_offset))?;
+            let mut buf = vec![0u8; frame.compressed_length as usize];
+            self.archive_file.read_exact(&mut buf)?;
+
+            if window_start.is_none() {
+                window_start = Some(frame.uncompressed_offset);
+            }
+            window.extend(self.decode_payload(&buf, codec)?);
+        }
+
+        let base = window_start.unwrap_or(offset);
+        let start = (offset - base) as usize;
+        let want_end = (start + (end - offset) as usize).min(window.len());
+        Ok(window[start..want_end].to_vec())
+    }
+
+    /// Returns a `Read + Seek` handle over `path` that decompresses only the
+    /// frames each read actually touches, for streaming access to files too
+    /// large to hold fully in memory.
+    pub fn open_handle<'a>(&'a mut self, path: &str) -> Result<WaxFileHandle<'a>, WaxError> {
+        let mut stmt = self.index_conn.prepare(
+            "SELECT original_size FROM files WHERE path = ?1"
+        )?;
+        let size: Option<u64> = stmt.query_row([path], |row| row.get(0)).optional()?;
+        let size = size.ok_or_else(|| WaxError::FileNotFound(path.to_string()))?;
+
+        Ok(WaxFileHandle {
+            reader: self,
+            path: path.to_string(),
+            position: 0,
+            size,
+        })
+    }
+
+    /// Streams every blob in the archive, recomputing its CRC32 and comparing
+    /// it against the stored checksum. Used by the `verify` subcommand. SQLite
+    /// reads the index lazily, so opening the archive alone does not check it;
+    /// this method's query against the `files` table fails with a `Sql` error
+    /// if the `index_offset`/`index_length` region is not a valid SQLite database.
+    pub fn verify_all(&mut self) -> Result<VerifyReport, WaxError> {
+        let mut stmt = self
+            .index_conn
+            .prepare("SELECT path, blob_offset, blob_length, checksum FROM files")?;
 
-        let decompressed = zstd::stream::decode_all(&compressed_buffer[..])?;
+        let rows: Vec<(String, u64, u64, u32)> = stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        drop(stmt);
 
-        Ok(decompressed)
+        let mut good = 0u64;
+        let mut bad = Vec::new();
+
+        for (path, offset, length, expected_checksum) in rows {
+            self.archive_file.seek(SeekFrom::Start(offset))?;
+            let mut buffer = vec![0u8; length as usize];
+
+            match self.archive_file.read_exact(&mut buffer) {
+                Ok(()) if crc32fast::hash(&buffer) == expected_checksum => good += 1,
+                _ => bad.push(path),
+            }
+        }
+
+        Ok(VerifyReport { good, bad })
     }
     
     pub fn get_mime_type(&self, path: &str) -> Result<String, WaxError> {
@@ -108,7 +324,7 @@ impl WaxReader {
     // NEW: Function to list all files
     pub fn list_files(&self) -> Result<Vec<WaxEntry>, WaxError> {
         let mut stmt = self.index_conn.prepare(
-            "SELECT path, mime_type, original_size FROM files ORDER BY path ASC"
+            "SELECT path, mime_type, original_size, content_hash FROM files ORDER BY path ASC"
         )?;
 
         let rows = stmt.query_map([], |row| {
@@ -116,6 +332,63 @@ impl WaxReader {
                 path: row.get(0)?,
                 mime_type: row.get::<_, Option<String>>(1)?.unwrap_or("unknown".to_string()),
                 size: row.get(2)?,
+                content_hash: row.get(3)?,
+            })
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            entries.push(row?);
+        }
+        Ok(entries)
+    }
+
+    /// Returns the `blob_offset` recorded for `path`. Several paths can share
+    /// the same offset after dedup.
+    pub fn get_blob_offset(&self, path: &str) -> Result<u64, WaxError> {
+        let mut stmt = self.index_conn.prepare(
+            "SELECT blob_offset FROM files WHERE path = ?1"
+        )?;
+
+        stmt.query_row([path], |row| row.get(0))
+            .optional()?
+            .ok_or_else(|| WaxError::FileNotFound(path.to_string()))
+    }
+
+    /// Returns the BLAKE3 content digest recorded for `path`, if the archive's
+    /// index has one (older archives built before dedup support won't).
+    pub fn get_content_hash(&self, path: &str) -> Result<Option<Vec<u8>>, WaxError> {
+        let mut stmt = self.index_conn.prepare(
+            "SELECT content_hash FROM files WHERE path = ?1"
+        )?;
+
+        let hash: Option<Option<Vec<u8>>> = stmt.query_row([path], |row| row.get(0)).optional()?;
+
+        match hash {
+            Some(h) => Ok(h),
+            None => Err(WaxError::FileNotFound(path.to_string())),
+        }
+    }
+
+    /// Runs an FTS5 `MATCH` query against the full-text bodies extracted
+    /// during `build_archive` (see `wax_core::extract`), returning matching
+    /// files ranked by relevance. Archives built before extraction support
+    /// won't have a `files_fts` table and will surface that as a `Sql` error.
+    pub fn search(&self, query: &str) -> Result<Vec<WaxEntry>, WaxError> {
+        let mut stmt = self.index_conn.prepare(
+            "SELECT f.path, f.mime_type, f.original_size, f.content_hash
+             FROM files_fts
+             JOIN files f ON f.id = files_fts.rowid
+             WHERE files_fts MATCH ?1
+             ORDER BY rank"
+        )?;
+
+        let rows = stmt.query_map([query], |row| {
+            Ok(WaxEntry {
+                path: row.get(0)?,
+                mime_type: row.get::<_, Option<String>>(1)?.unwrap_or("unknown".to_string()),
+                size: row.get(2)?,
+                content_hash: row.get(3)?,
             })
         })?;
 
@@ -125,4 +398,53 @@ impl WaxReader {
         }
         Ok(entries)
     }
+}
+
+/// A streaming, seekable view of one archive member, backed by
+/// [`WaxReader::read_range`]. Reads only decompress the frames a given
+/// position actually falls in.
+pub struct WaxFileHandle<'a> {
+    reader: &'a mut WaxReader,
+    path: String,
+    position: u64,
+    size: u64,
+}
+
+impl<'a> Read for WaxFileHandle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let remaining = self.size.saturating_sub(self.position);
+        let want = (buf.len() as u64).min(remaining);
+        if want == 0 {
+            return Ok(0);
+        }
+
+        let data = self
+            .reader
+            .read_range(&self.path, self.position, want)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
+
+        buf[..data.len()].copy_from_slice(&data);
+        self.position += data.len() as u64;
+        Ok(data.len())
+    }
+}
+
+impl<'a> Seek for WaxFileHandle<'a> {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(offset) => offset as i64,
+            SeekFrom::End(offset) => self.size as i64 + offset,
+            SeekFrom::Current(offset) => self.position as i64 + offset,
+        };
+
+        if new_pos < 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "seek position would be negative",
+            ));
+        }
+
+        self.position = new_pos as u64;
+        Ok(self.position)
+    }
 }
\ No newline at end of file
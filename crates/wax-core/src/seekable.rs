@@ -0,0 +1,106 @@
+//! Frame index for seekable blobs: large files are compressed as a sequence
+//! of independently-decompressable frames instead of one monolithic blob, so
+//! `WaxReader::read_range` can decompress only the frames a caller actually
+//! asked for.
+
+/// Uncompressed bytes per frame. Files at or below this size use the
+/// existing single-shot blob path instead.
+pub const FRAME_SIZE: u64 = 1024 * 1024;
+
+/// One frame's place in the uncompressed stream and in the blob on disk.
+/// `compressed_offset` is relative to the start of the blob (i.e. to the
+/// file's `blob_offset`), not to the start of the archive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FrameEntry {
+    pub uncompressed_offset: u64,
+    pub uncompressed_length: u64,
+    pub compressed_offset: u64,
+    pub compressed_length: u64,
+}
+
+const ENTRY_LEN: usize = 32;
+
+/// Packs a frame table into the bytes stored in the `frame_index` column.
+pub fn encode_frame_index(frames: &[FrameEntry]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(frames.len() * ENTRY_LEN);
+    for frame in frames {
+        out.extend_from_slice(&frame.uncompressed_offset.to_le_bytes());
+        out.extend_from_slice(&frame.uncompressed_length.to_le_bytes());
+        out.extend_from_slice(&frame.compressed_offset.to_le_bytes());
+        out.extend_from_slice(&frame.compressed_length.to_le_bytes());
+    }
+    out
+}
+
+/// Reverses [`encode_frame_index`].
+pub fn decode_frame_index(bytes: &[u8]) -> Vec<FrameEntry> {
+    bytes
+        .chunks_exact(ENTRY_LEN)
+        .map(|chunk| FrameEntry {
+            uncompressed_offset: u64::from_le_bytes(chunk[0..8].try_into().unwrap()),
+            uncompressed_length: u64::from_le_bytes(chunk[8..16].try_into().unwrap()),
+            compressed_offset: u64::from_le_bytes(chunk[16..24].try_into().unwrap()),
+            compressed_length: u64::from_le_bytes(chunk[24..32].try_into().unwrap()),
+        })
+        .collect()
+}
+
+/// Returns the frames whose uncompressed range overlaps `[offset, offset + len)`.
+pub fn frames_covering(frames: &[FrameEntry], offset: u64, len: u64) -> Vec<FrameEntry> {
+    let end = offset + len;
+    frames
+        .iter()
+        .copied()
+        .filter(|f| f.uncompressed_offset < end && f.uncompressed_offset + f.uncompressed_length > offset)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_frames() -> Vec<FrameEntry> {
+        // Three 10-byte uncompressed frames, packed back to back in the blob.
+        vec![
+            FrameEntry { uncompressed_offset: 0, uncompressed_length: 10, compressed_offset: 0, compressed_length: 6 },
+            FrameEntry { uncompressed_offset: 10, uncompressed_length: 10, compressed_offset: 6, compressed_length: 7 },
+            FrameEntry { uncompressed_offset: 20, uncompressed_length: 10, compressed_offset: 13, compressed_length: 5 },
+        ]
+    }
+
+    #[test]
+    fn encode_decode_round_trips() {
+        let frames = sample_frames();
+        assert_eq!(decode_frame_index(&encode_frame_index(&frames)), frames);
+    }
+
+    #[test]
+    fn decode_of_empty_bytes_is_empty() {
+        assert!(decode_frame_index(&[]).is_empty());
+    }
+
+    #[test]
+    fn frames_covering_finds_only_overlapping_frames() {
+        let frames = sample_frames();
+
+        // Entirely within the second frame.
+        assert_eq!(frames_covering(&frames, 12, 3), vec![frames[1]]);
+
+        // Spans the boundary between the first two frames.
+        assert_eq!(frames_covering(&frames, 8, 4), vec![frames[0], frames[1]]);
+
+        // Touches all three.
+        assert_eq!(frames_covering(&frames, 5, 20), frames);
+    }
+
+    #[test]
+    fn frames_covering_excludes_adjacent_non_overlapping_frames() {
+        let frames = sample_frames();
+
+        // [10, 15) starts exactly where frame 0 ends, so frame 0 shouldn't match.
+        assert_eq!(frames_covering(&frames, 10, 5), vec![frames[1]]);
+
+        // Zero-length window matches nothing.
+        assert!(frames_covering(&frames, 10, 0).is_empty());
+    }
+}